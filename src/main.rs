@@ -1,45 +1,982 @@
-use accesskit::TreeUpdate;
+use accesskit::{
+    Action, DefaultActionVerb, NodeBuilder, NodeClassSet, NodeId, Rect, Role, Tree, TreeUpdate,
+};
 use accesskit_winit::{ActionRequestEvent, Adapter};
 use gl::types::*;
 use glutin::{
-    config::{ConfigTemplateBuilder, GlConfig},
+    config::{Config, ConfigTemplateBuilder, GlConfig},
     context::{
-        ContextApi, ContextAttributesBuilder, NotCurrentGlContextSurfaceAccessor,
-        PossiblyCurrentContext,
+        ContextApi, ContextAttributesBuilder, NotCurrentContext,
+        NotCurrentGlContextSurfaceAccessor, PossiblyCurrentContext,
+        PossiblyCurrentContextGlSurfaceAccessor,
     },
     display::{GetGlDisplay, GlDisplay},
     prelude::GlSurface,
     surface::{Surface as GlutinSurface, SurfaceAttributesBuilder, WindowSurface},
 };
 use glutin_winit::DisplayBuilder;
-use raw_window_handle::HasRawWindowHandle;
-use skia_safe::{textlayout::FontCollection, Font, FontMgr, FontStyle, Paint, Point};
-use winit::event_loop::EventLoopBuilder;
+use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
+use skia_safe::{textlayout::FontCollection, Canvas, Font, FontMgr, FontStyle, Paint, Point};
 
 use std::{ffi::CString, num::NonZeroU32};
 
 use winit::{
-    event::{Event, WindowEvent},
-    event_loop::ControlFlow,
-    window::{Window, WindowBuilder},
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    window::{Window, WindowAttributes, WindowId},
 };
 
 use skia_safe::{
-    gpu::{gl::FramebufferInfo, BackendRenderTarget, SurfaceOrigin},
+    gpu::{gl::FramebufferInfo, BackendRenderTarget, BackendSurfaceAccess, FlushInfo, SurfaceOrigin},
     Color, ColorType, Surface,
 };
 
-fn main() {
-    let el = EventLoopBuilder::<ActionRequestEvent>::with_user_event().build();
-    let winit_window_builder = WindowBuilder::new().with_title("rust-skia-gl-window");
+use ash::{extensions::khr, vk, vk::Handle};
+use std::ffi::c_void;
+
+/// Root of the accessibility tree — the window container node.
+const ROOT_ID: NodeId = NodeId(1);
+/// The single "Hello World" text node.
+const TEXT_ID: NodeId = NodeId(2);
+
+/// A node in the declarative UI tree. A single traversal turns each element
+/// into both skia draw calls and an AccessKit node, so content is described
+/// once and kept in sync between painting and accessibility.
+#[derive(Clone)]
+struct Element {
+    id: NodeId,
+    role: Role,
+    bounds: Rect,
+    background_color: Option<Color>,
+    text: Option<String>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    /// Emit this element and its descendants as AccessKit nodes.
+    fn collect(&self, classes: &mut NodeClassSet, nodes: &mut Vec<(NodeId, accesskit::Node)>) {
+        let mut builder = NodeBuilder::new(self.role);
+        if !self.children.is_empty() {
+            builder.set_children(self.children.iter().map(|c| c.id).collect::<Vec<_>>());
+        }
+        if let Some(text) = &self.text {
+            builder.set_name(text.clone());
+        }
+        builder.set_bounds(self.bounds);
+        if self.role != Role::Window {
+            builder.add_action(Action::Focus);
+            builder.set_default_action_verb(DefaultActionVerb::Click);
+        }
+        nodes.push((self.id, builder.build(classes)));
+
+        for child in &self.children {
+            child.collect(classes, nodes);
+        }
+    }
+
+    /// Paint this element and its descendants onto the canvas.
+    fn paint(&self, canvas: &Canvas, font: &Font, paint: &Paint) {
+        if let Some(color) = self.background_color {
+            let mut bg = Paint::default();
+            bg.set_color(color);
+            canvas.draw_rect(to_skia_rect(self.bounds), &bg);
+        }
+        if let Some(text) = &self.text {
+            // Baseline sits at the bottom edge of the element bounds.
+            let origin = Point::new(self.bounds.x0 as f32, self.bounds.y1 as f32);
+            canvas.draw_str(text, origin, font, paint);
+        }
+        for child in &self.children {
+            child.paint(canvas, font, paint);
+        }
+    }
+
+    fn contains(&self, id: NodeId) -> bool {
+        self.id == id || self.children.iter().any(|child| child.contains(id))
+    }
+}
+
+fn to_skia_rect(bounds: Rect) -> skia_safe::Rect {
+    skia_safe::Rect::new(
+        bounds.x0 as f32,
+        bounds.y0 as f32,
+        bounds.x1 as f32,
+        bounds.y1 as f32,
+    )
+}
+
+/// The element tree plus a dirty flag; mutating the tree sets `dirty` so the
+/// next `about_to_wait` schedules a redraw.
+#[derive(Clone)]
+struct Scene {
+    root: Element,
+    dirty: bool,
+}
+
+impl Scene {
+    /// Walk the tree into a `TreeUpdate` with the root element as the tree root.
+    fn tree_update(&self, focus: NodeId) -> TreeUpdate {
+        let mut classes = NodeClassSet::new();
+        let mut nodes = Vec::new();
+        self.root.collect(&mut classes, &mut nodes);
+        TreeUpdate {
+            nodes,
+            tree: Some(Tree::new(self.root.id)),
+            focus,
+        }
+    }
+
+    fn paint(&self, canvas: &Canvas, font: &Font, paint: &Paint) {
+        self.root.paint(canvas, font, paint);
+    }
+
+    fn contains(&self, id: NodeId) -> bool {
+        self.root.contains(id)
+    }
+}
+
+/// The content currently on screen: a white window containing the greeting.
+fn hello_world_scene() -> Scene {
+    Scene {
+        root: Element {
+            id: ROOT_ID,
+            role: Role::Window,
+            bounds: Rect {
+                x0: 0.0,
+                y0: 0.0,
+                x1: 600.0,
+                y1: 300.0,
+            },
+            background_color: Some(Color::WHITE),
+            text: None,
+            children: vec![Element {
+                id: TEXT_ID,
+                role: Role::Label,
+                bounds: Rect {
+                    x0: 30.0,
+                    y0: 50.0,
+                    x1: 560.0,
+                    y1: 150.0,
+                },
+                background_color: None,
+                text: Some("Hello World".to_string()),
+                children: vec![],
+            }],
+        },
+        dirty: true,
+    }
+}
+
+/// The parts of the renderer that are only valid while the window has a live
+/// surface, i.e. between `resumed` and `suspended`. On Android the
+/// `RawWindowHandle` behind these is handed out and reclaimed by the OS, so
+/// they must be recreated on resume and dropped on suspend.
+struct RenderState {
+    surface: Surface,
+    gl_surface: GlutinSurface<WindowSurface>,
+    gl_context: PossiblyCurrentContext,
+}
+
+struct Env {
+    renderer: Renderer,
+    adapter: Adapter,
+    scene: Scene,
+    /// The typeface used for text, resolved once at setup instead of per frame.
+    font: Font,
+    focus: NodeId,
+    window: Window,
+}
+
+/// Where the GL context and skia live. `Inline` keeps them on the event-loop
+/// thread; `Threaded` relocates them to a worker so rendering does not block
+/// input handling and vice-versa. Selected at startup via `SKIA_RENDER_THREAD`.
+enum Renderer {
+    Inline(Box<dyn RenderBackend>),
+    Threaded(ThreadedRenderer),
+}
+
+/// The OpenGL backend: context and skia objects bound to a GL surface.
+struct GlBackend {
+    /// Present only while the window is active.
+    render: Option<RenderState>,
+    /// The context while the window is suspended; moved back out on resume.
+    not_current_context: Option<NotCurrentContext>,
+    gr_context: skia_safe::gpu::DirectContext,
+    gl_config: Config,
+    fb_info: FramebufferInfo,
+    num_samples: usize,
+    stencil_size: usize,
+}
+
+fn create_surface(
+    fb_info: FramebufferInfo,
+    gr_context: &mut skia_safe::gpu::DirectContext,
+    num_samples: usize,
+    stencil_size: usize,
+    width: u32,
+    height: u32,
+) -> Surface {
+    let size = (width.max(1) as i32, height.max(1) as i32);
+    let backend_render_target =
+        BackendRenderTarget::new_gl(size, num_samples, stencil_size, fb_info);
+
+    Surface::from_backend_render_target(
+        gr_context,
+        &backend_render_target,
+        SurfaceOrigin::BottomLeft,
+        ColorType::RGBA8888,
+        None,
+        None,
+    )
+    .expect("Could not create skia surface")
+}
+
+/// Resolve the "Inter" typeface at size 100 from the default font manager.
+fn load_font() -> Font {
+    let mgr = FontMgr::default();
+    let mut font_coll = FontCollection::new();
+    font_coll.set_default_font_manager(mgr, "Inter");
+    Font::from_typeface(
+        font_coll
+            .find_typefaces(&["Inter"], FontStyle::default())
+            .first()
+            .unwrap(),
+        100.0,
+    )
+}
+
+/// Paint a scene onto a skia surface and present it through the GL surface.
+fn paint_scene(
+    surface: &mut Surface,
+    gr_context: &mut skia_safe::gpu::DirectContext,
+    gl_surface: &GlutinSurface<WindowSurface>,
+    gl_context: &PossiblyCurrentContext,
+    scene: &Scene,
+    font: &Font,
+) {
+    println!("DRAWING");
+    let canvas = surface.canvas();
+    canvas.clear(Color::WHITE);
+    let mut paint = Paint::default();
+    paint.set_color(Color::BLUE);
+    scene.paint(canvas, font, &paint);
+    gr_context.flush_and_submit();
+    gl_surface.swap_buffers(gl_context).unwrap();
+}
+
+/// A swappable rendering backend. The same scene and accessibility code draws
+/// on top of any implementation; only the GPU plumbing differs. Selected at
+/// startup via `SKIA_BACKEND`.
+trait RenderBackend {
+    /// (Re)build the drawing surface for the current window. Called on resume.
+    fn create_surface(&mut self, window: &Window);
+    /// Release the drawing surface when the window goes away (suspend). The
+    /// default is a no-op for backends whose surface lives for the program.
+    fn destroy_surface(&mut self) {}
+    /// Resize the drawing surface to the new physical size.
+    fn resize(&mut self, width: u32, height: u32);
+    /// Paint the scene and present it to the window.
+    fn present(&mut self, scene: &Scene, font: &Font);
+}
+
+impl RenderBackend for GlBackend {
+    /// (Re)build the GL window surface and skia `Surface` for the current
+    /// window handle and make the context current. Called from the resume path.
+    fn create_surface(&mut self, window: &Window) {
+        if self.render.is_some() {
+            return;
+        }
+        let not_current = self
+            .not_current_context
+            .take()
+            .expect("context already current");
+
+        let size = window.inner_size();
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        let raw_window_handle = window.raw_window_handle();
+        let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+            raw_window_handle,
+            NonZeroU32::new(width).unwrap(),
+            NonZeroU32::new(height).unwrap(),
+        );
+        let gl_surface = unsafe {
+            self.gl_config
+                .display()
+                .create_window_surface(&self.gl_config, &attrs)
+                .expect("Could not create gl window surface")
+        };
+        let gl_context = not_current
+            .make_current(&gl_surface)
+            .expect("Could not make GL context current when setting up skia renderer");
+
+        let surface = create_surface(
+            self.fb_info,
+            &mut self.gr_context,
+            self.num_samples,
+            self.stencil_size,
+            width,
+            height,
+        );
+
+        self.render = Some(RenderState {
+            surface,
+            gl_surface,
+            gl_context,
+        });
+    }
+
+    /// Tear the surface down when the window goes away, parking the context in
+    /// its not-current state so it survives until the next resume.
+    fn destroy_surface(&mut self) {
+        if let Some(render) = self.render.take() {
+            let not_current = render
+                .gl_context
+                .make_not_current()
+                .expect("Could not make GL context not current on suspend");
+            self.not_current_context = Some(not_current);
+            // `render.gl_surface` and `render.surface` drop here.
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        if let Some(render) = self.render.as_mut() {
+            render.surface = create_surface(
+                self.fb_info,
+                &mut self.gr_context,
+                self.num_samples,
+                self.stencil_size,
+                width,
+                height,
+            );
+            /* Then resize the opengl drawable */
+            render.gl_surface.resize(
+                &render.gl_context,
+                NonZeroU32::new(width.max(1)).unwrap(),
+                NonZeroU32::new(height.max(1)).unwrap(),
+            );
+        }
+    }
+
+    fn present(&mut self, scene: &Scene, font: &Font) {
+        if let Some(render) = self.render.as_mut() {
+            paint_scene(
+                &mut render.surface,
+                &mut self.gr_context,
+                &render.gl_surface,
+                &render.gl_context,
+                scene,
+                font,
+            );
+        }
+    }
+}
+
+/// Messages the event loop sends to the render thread.
+enum RenderCommand {
+    /// A freshly created GL window surface to render into, with its size. Sent
+    /// on resume; the worker makes the context current on it.
+    Resume(GlutinSurface<WindowSurface>, NonZeroU32, NonZeroU32),
+    /// Release the GL surface and make the context not-current (suspend).
+    Suspend,
+    Resize(NonZeroU32, NonZeroU32),
+    Redraw(Scene),
+    Shutdown,
+}
+
+/// Handle to the worker that owns the GL context and skia on a separate thread.
+struct ThreadedRenderer {
+    sender: std::sync::mpsc::Sender<RenderCommand>,
+    handle: Option<std::thread::JoinHandle<()>>,
+    /// Kept on the event-loop thread so we can build GL window surfaces (which
+    /// must come from the display) and hand them to the worker on resume.
+    gl_config: Config,
+}
+
+impl ThreadedRenderer {
+    /// Build a GL window surface for the current window and hand it to the
+    /// worker so it can make the context current again after a suspend.
+    fn create_surface(&self, window: &Window) {
+        let size = window.inner_size();
+        let (width, height) = (size.width.max(1), size.height.max(1));
+        let attrs = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+            window.raw_window_handle(),
+            NonZeroU32::new(width).unwrap(),
+            NonZeroU32::new(height).unwrap(),
+        );
+        let gl_surface = unsafe {
+            self.gl_config
+                .display()
+                .create_window_surface(&self.gl_config, &attrs)
+                .expect("Could not create gl window surface")
+        };
+        let _ = self.sender.send(RenderCommand::Resume(
+            gl_surface,
+            NonZeroU32::new(width).unwrap(),
+            NonZeroU32::new(height).unwrap(),
+        ));
+    }
+
+    fn destroy_surface(&self) {
+        let _ = self.sender.send(RenderCommand::Suspend);
+    }
+
+    fn resize(&self, width: u32, height: u32) {
+        let _ = self.sender.send(RenderCommand::Resize(
+            NonZeroU32::new(width.max(1)).unwrap(),
+            NonZeroU32::new(height.max(1)).unwrap(),
+        ));
+    }
+
+    fn draw(&self, scene: Scene) {
+        let _ = self.sender.send(RenderCommand::Redraw(scene));
+    }
+}
+
+impl Drop for ThreadedRenderer {
+    fn drop(&mut self) {
+        let _ = self.sender.send(RenderCommand::Shutdown);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// The active drawing state on the render thread, valid only between resume and
+/// suspend.
+struct ActiveGl {
+    gl_context: PossiblyCurrentContext,
+    gl_surface: GlutinSurface<WindowSurface>,
+    surface: Surface,
+}
+
+/// The render thread entry point: make the not-current context current on this
+/// thread, build skia, then service draw/resize/suspend/resume commands until
+/// shutdown. The context is `!Sync` and bound to whichever thread makes it
+/// current, so it is handed over not-current and only ever made current here.
+///
+/// The worker mirrors the inline backend's `Resumed`/`Suspended` lifecycle: the
+/// GL surface is built on the event-loop thread and handed over on resume, and
+/// released (context made not-current) on suspend.
+fn render_worker(
+    gl_config: Config,
+    not_current: NotCurrentContext,
+    gl_surface: GlutinSurface<WindowSurface>,
+    receiver: std::sync::mpsc::Receiver<RenderCommand>,
+) {
+    // Bootstrap skia by making the context current once, then park it
+    // not-current until the first `Resume` hands over a live surface.
+    let gl_context = not_current
+        .make_current(&gl_surface)
+        .expect("Could not make GL context current on render thread");
+
+    gl::load_with(|s| {
+        gl_config
+            .display()
+            .get_proc_address(CString::new(s).unwrap().as_c_str())
+    });
+    let interface = skia_safe::gpu::gl::Interface::new_load_with(|name| {
+        if name == "eglGetCurrentDisplay" {
+            return std::ptr::null();
+        }
+        gl_config
+            .display()
+            .get_proc_address(CString::new(name).unwrap().as_c_str())
+    })
+    .expect("Could not create interface");
+
+    let mut gr_context = skia_safe::gpu::DirectContext::new_gl(Some(interface), None)
+        .expect("Could not create direct context");
+
+    let fb_info = {
+        let mut fboid: GLint = 0;
+        unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
+
+        FramebufferInfo {
+            fboid: fboid.try_into().unwrap(),
+            format: skia_safe::gpu::gl::Format::RGBA8.into(),
+        }
+    };
+
+    let num_samples = gl_config.num_samples() as usize;
+    let stencil_size = gl_config.stencil_size() as usize;
+    let font = load_font();
+
+    let mut parked = Some(
+        gl_context
+            .make_not_current()
+            .expect("Could not make GL context not current on render thread"),
+    );
+    drop(gl_surface);
+    let mut active: Option<ActiveGl> = None;
+
+    while let Ok(command) = receiver.recv() {
+        match command {
+            RenderCommand::Resume(gl_surface, width, height) => {
+                if active.is_none() {
+                    let not_current = parked.take().expect("context already current on worker");
+                    let gl_context = not_current
+                        .make_current(&gl_surface)
+                        .expect("Could not make GL context current on resume");
+                    let surface = create_surface(
+                        fb_info,
+                        &mut gr_context,
+                        num_samples,
+                        stencil_size,
+                        width.get(),
+                        height.get(),
+                    );
+                    active = Some(ActiveGl {
+                        gl_context,
+                        gl_surface,
+                        surface,
+                    });
+                }
+            }
+            RenderCommand::Suspend => {
+                if let Some(active) = active.take() {
+                    parked = Some(
+                        active
+                            .gl_context
+                            .make_not_current()
+                            .expect("Could not make GL context not current on suspend"),
+                    );
+                    // `active.gl_surface` and `active.surface` drop here.
+                }
+            }
+            RenderCommand::Resize(width, height) => {
+                if let Some(active) = active.as_mut() {
+                    active.surface = create_surface(
+                        fb_info,
+                        &mut gr_context,
+                        num_samples,
+                        stencil_size,
+                        width.get(),
+                        height.get(),
+                    );
+                    active.gl_surface.resize(&active.gl_context, width, height);
+                }
+            }
+            RenderCommand::Redraw(scene) => {
+                if let Some(active) = active.as_mut() {
+                    paint_scene(
+                        &mut active.surface,
+                        &mut gr_context,
+                        &active.gl_surface,
+                        &active.gl_context,
+                        &scene,
+                        &font,
+                    );
+                }
+            }
+            RenderCommand::Shutdown => break,
+        }
+    }
+}
+
+/// A swapchain plus the per-image skia surfaces skia renders into.
+struct Swapchain {
+    handle: vk::SwapchainKHR,
+    surfaces: Vec<Surface>,
+    /// Signalled by `acquire_next_image`; waited on before rendering so we never
+    /// draw into an image the presentation engine is still reading.
+    acquire_fence: vk::Fence,
+}
+
+/// The Vulkan backend: skia renders through `DirectContext::new_vulkan`, and the
+/// presentation images come from a swapchain managed directly with `ash`.
+struct VulkanBackend {
+    // Kept alive for the lifetime of the instance it loaded; never read directly.
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    surface_loader: khr::Surface,
+    surface: vk::SurfaceKHR,
+    physical_device: vk::PhysicalDevice,
+    device: ash::Device,
+    queue: vk::Queue,
+    swapchain_loader: khr::Swapchain,
+    surface_format: vk::SurfaceFormatKHR,
+    gr_context: skia_safe::gpu::DirectContext,
+    swapchain: Option<Swapchain>,
+}
+
+impl VulkanBackend {
+    fn new(window: &Window) -> VulkanBackend {
+        let entry = unsafe { ash::Entry::load() }.expect("Could not load Vulkan entry points");
+
+        let app_info =
+            vk::ApplicationInfo::builder().api_version(vk::make_api_version(0, 1, 1, 0));
+        let instance_extensions =
+            ash_window::enumerate_required_extensions(window.raw_display_handle())
+                .expect("Could not enumerate required Vulkan extensions");
+        let instance_ci = vk::InstanceCreateInfo::builder()
+            .application_info(&app_info)
+            .enabled_extension_names(instance_extensions);
+        let instance =
+            unsafe { entry.create_instance(&instance_ci, None) }.expect("Could not create instance");
+
+        let surface = unsafe {
+            ash_window::create_surface(
+                &entry,
+                &instance,
+                window.raw_display_handle(),
+                window.raw_window_handle(),
+                None,
+            )
+        }
+        .expect("Could not create Vulkan surface");
+        let surface_loader = khr::Surface::new(&entry, &instance);
+
+        // Pick the first device with a queue family that can both render and
+        // present to our surface.
+        let (physical_device, queue_family) = unsafe { instance.enumerate_physical_devices() }
+            .expect("Could not enumerate physical devices")
+            .into_iter()
+            .find_map(|pd| {
+                let families = unsafe { instance.get_physical_device_queue_family_properties(pd) };
+                families.iter().enumerate().find_map(|(index, family)| {
+                    let graphics = family.queue_flags.contains(vk::QueueFlags::GRAPHICS);
+                    let present = unsafe {
+                        surface_loader.get_physical_device_surface_support(
+                            pd,
+                            index as u32,
+                            surface,
+                        )
+                    }
+                    .unwrap_or(false);
+                    (graphics && present).then_some((pd, index as u32))
+                })
+            })
+            .expect("No Vulkan device can present to the window surface");
+
+        let priorities = [1.0f32];
+        let queue_ci = vk::DeviceQueueCreateInfo::builder()
+            .queue_family_index(queue_family)
+            .queue_priorities(&priorities);
+        let device_extensions = [khr::Swapchain::name().as_ptr()];
+        let device_ci = vk::DeviceCreateInfo::builder()
+            .queue_create_infos(std::slice::from_ref(&queue_ci))
+            .enabled_extension_names(&device_extensions);
+        let device = unsafe { instance.create_device(physical_device, &device_ci, None) }
+            .expect("Could not create logical device");
+        let queue = unsafe { device.get_device_queue(queue_family, 0) };
+        let swapchain_loader = khr::Swapchain::new(&instance, &device);
+
+        // Prefer a plain BGRA8 UNORM surface, falling back to whatever is first.
+        let formats =
+            unsafe { surface_loader.get_physical_device_surface_formats(physical_device, surface) }
+                .expect("Could not query surface formats");
+        let surface_format = formats
+            .iter()
+            .copied()
+            .find(|f| f.format == vk::Format::B8G8R8A8_UNORM)
+            .unwrap_or_else(|| formats[0]);
+
+        let get_proc = |of| unsafe {
+            match of {
+                skia_safe::gpu::vk::GetProcOf::Instance(raw_instance, name) => {
+                    let instance = ash::vk::Instance::from_raw(raw_instance as u64);
+                    entry
+                        .get_instance_proc_addr(instance, name)
+                        .map_or(std::ptr::null(), |f| f as *const c_void)
+                }
+                skia_safe::gpu::vk::GetProcOf::Device(raw_device, name) => {
+                    let device = ash::vk::Device::from_raw(raw_device as u64);
+                    (instance.fp_v1_0().get_device_proc_addr)(device, name)
+                        .map_or(std::ptr::null(), |f| f as *const c_void)
+                }
+            }
+        };
+
+        let backend_context = unsafe {
+            skia_safe::gpu::vk::BackendContext::new(
+                instance.handle().as_raw() as *mut c_void,
+                physical_device.as_raw() as *mut c_void,
+                device.handle().as_raw() as *mut c_void,
+                (queue.as_raw() as *mut c_void, queue_family as usize),
+                &get_proc,
+            )
+        };
+        let gr_context = skia_safe::gpu::DirectContext::new_vulkan(&backend_context, None)
+            .expect("Could not create Vulkan direct context");
+
+        VulkanBackend {
+            _entry: entry,
+            instance,
+            surface_loader,
+            surface,
+            physical_device,
+            device,
+            queue,
+            swapchain_loader,
+            surface_format,
+            gr_context,
+            swapchain: None,
+        }
+    }
+
+    /// Build the swapchain and a skia `Surface` wrapping each of its images.
+    fn build_swapchain(&mut self, width: u32, height: u32) {
+        let caps = unsafe {
+            self.surface_loader
+                .get_physical_device_surface_capabilities(self.physical_device, self.surface)
+        }
+        .expect("Could not query surface capabilities");
+
+        let extent = if caps.current_extent.width == u32::MAX {
+            vk::Extent2D {
+                width: width.max(1),
+                height: height.max(1),
+            }
+        } else {
+            caps.current_extent
+        };
+        let image_count = (caps.min_image_count + 1).min(if caps.max_image_count == 0 {
+            u32::MAX
+        } else {
+            caps.max_image_count
+        });
+
+        let swapchain_ci = vk::SwapchainCreateInfoKHR::builder()
+            .surface(self.surface)
+            .min_image_count(image_count)
+            .image_format(self.surface_format.format)
+            .image_color_space(self.surface_format.color_space)
+            .image_extent(extent)
+            .image_array_layers(1)
+            .image_usage(vk::ImageUsageFlags::COLOR_ATTACHMENT)
+            .image_sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .pre_transform(caps.current_transform)
+            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .present_mode(vk::PresentModeKHR::FIFO)
+            .clipped(true);
+        let handle = unsafe { self.swapchain_loader.create_swapchain(&swapchain_ci, None) }
+            .expect("Could not create swapchain");
+        let images = unsafe { self.swapchain_loader.get_swapchain_images(handle) }
+            .expect("Could not get swapchain images");
+
+        let surfaces = images
+            .iter()
+            .map(|image| {
+                let image_info = unsafe {
+                    skia_safe::gpu::vk::ImageInfo::new(
+                        image.as_raw() as *mut c_void,
+                        skia_safe::gpu::vk::Alloc::default(),
+                        skia_safe::gpu::vk::ImageTiling::OPTIMAL,
+                        skia_safe::gpu::vk::ImageLayout::UNDEFINED,
+                        self.surface_format.format.as_raw() as u32,
+                        1,
+                        None,
+                        None,
+                        None,
+                        None,
+                    )
+                };
+                let render_target = &BackendRenderTarget::new_vulkan(
+                    (extent.width as i32, extent.height as i32),
+                    &image_info,
+                );
+                Surface::from_backend_render_target(
+                    &mut self.gr_context,
+                    render_target,
+                    SurfaceOrigin::TopLeft,
+                    ColorType::BGRA8888,
+                    None,
+                    None,
+                )
+                .expect("Could not create skia surface for swapchain image")
+            })
+            .collect();
+
+        let acquire_fence = unsafe {
+            self.device
+                .create_fence(&vk::FenceCreateInfo::default(), None)
+        }
+        .expect("Could not create acquire fence");
+
+        self.swapchain = Some(Swapchain {
+            handle,
+            surfaces,
+            acquire_fence,
+        });
+    }
+
+    fn destroy_swapchain(&mut self) {
+        if let Some(swapchain) = self.swapchain.take() {
+            unsafe {
+                let _ = self.device.device_wait_idle();
+                // skia surfaces drop first, releasing their image references.
+                drop(swapchain.surfaces);
+                self.device.destroy_fence(swapchain.acquire_fence, None);
+                self.swapchain_loader.destroy_swapchain(swapchain.handle, None);
+            }
+        }
+    }
+}
+
+impl RenderBackend for VulkanBackend {
+    fn create_surface(&mut self, window: &Window) {
+        if self.swapchain.is_some() {
+            return;
+        }
+        let size = window.inner_size();
+        self.build_swapchain(size.width, size.height);
+    }
+
+    fn destroy_surface(&mut self) {
+        self.destroy_swapchain();
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.destroy_swapchain();
+        self.build_swapchain(width, height);
+    }
+
+    fn present(&mut self, scene: &Scene, font: &Font) {
+        let Some(swapchain) = self.swapchain.as_mut() else {
+            return;
+        };
+        // Acquire into a fence (a non-null sync object is required) and wait on
+        // it so the image is free before skia renders into it.
+        unsafe {
+            let _ = self.device.reset_fences(&[swapchain.acquire_fence]);
+        }
+        let acquire = unsafe {
+            self.swapchain_loader.acquire_next_image(
+                swapchain.handle,
+                u64::MAX,
+                vk::Semaphore::null(),
+                swapchain.acquire_fence,
+            )
+        };
+        let Ok((index, _suboptimal)) = acquire else {
+            // Surface out of date; it will be rebuilt on the next resize.
+            return;
+        };
+        unsafe {
+            let _ = self
+                .device
+                .wait_for_fences(&[swapchain.acquire_fence], true, u64::MAX);
+        }
+
+        println!("DRAWING");
+        let surface = &mut swapchain.surfaces[index as usize];
+        let canvas = surface.canvas();
+        canvas.clear(Color::WHITE);
+        let mut paint = Paint::default();
+        paint.set_color(Color::BLUE);
+        scene.paint(canvas, font, &paint);
+
+        // Flush with `Present` access so skia transitions the image to
+        // `PRESENT_SRC_KHR`, then wait for the GPU so we never present an image
+        // skia is still rendering into.
+        self.gr_context.flush_surface_with_access(
+            surface,
+            BackendSurfaceAccess::Present,
+            &FlushInfo::default(),
+        );
+        self.gr_context.submit(None);
+        unsafe {
+            let _ = self.device.queue_wait_idle(self.queue);
+        }
+
+        let handles = [swapchain.handle];
+        let indices = [index];
+        let present_info = vk::PresentInfoKHR::builder()
+            .swapchains(&handles)
+            .image_indices(&indices);
+        let _ = unsafe {
+            self.swapchain_loader
+                .queue_present(self.queue, &present_info)
+        };
+    }
+}
+
+impl Drop for VulkanBackend {
+    fn drop(&mut self) {
+        self.destroy_swapchain();
+        unsafe {
+            // Abandon the skia context before tearing down the device it depends
+            // on, so it makes no further Vulkan calls when it is dropped later.
+            self.gr_context.abandon();
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+impl Env {
+    /// (Re)build the drawing surface on resume.
+    fn create_surface(&mut self) {
+        match &mut self.renderer {
+            Renderer::Inline(inline) => inline.create_surface(&self.window),
+            Renderer::Threaded(threaded) => threaded.create_surface(&self.window),
+        }
+    }
+
+    /// Release the drawing surface on suspend.
+    fn destroy_surface(&mut self) {
+        match &mut self.renderer {
+            Renderer::Inline(inline) => inline.destroy_surface(),
+            Renderer::Threaded(threaded) => threaded.destroy_surface(),
+        }
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        match &mut self.renderer {
+            Renderer::Inline(inline) => inline.resize(width, height),
+            Renderer::Threaded(threaded) => threaded.resize(width, height),
+        }
+    }
+
+    fn draw(&mut self) {
+        match &mut self.renderer {
+            Renderer::Inline(inline) => inline.present(&self.scene, &self.font),
+            Renderer::Threaded(threaded) => threaded.draw(self.scene.clone()),
+        }
+        self.scene.dirty = false;
+    }
+
+    /// Push the current content and focus to the accessibility adapter, if a
+    /// client is listening.
+    fn update_accessibility(&mut self) {
+        let update = self.scene.tree_update(self.focus);
+        self.adapter.update_if_active(|| update);
+    }
+
+    /// Route an incoming `ActionRequest` to the matching element.
+    fn handle_action_request(&mut self, request: accesskit::ActionRequest) {
+        if !self.scene.contains(request.target) {
+            return;
+        }
+        match request.action {
+            Action::Focus | Action::Default => self.focus = request.target,
+            _ => return,
+        }
+        self.update_accessibility();
+        // Mutating the tree marks it dirty; `about_to_wait` schedules the redraw.
+        self.scene.dirty = true;
+    }
+}
+
+/// Build the window, GL config and context, and the skia `DirectContext`. The
+/// returned `Env` owns everything that survives a suspend. The inline OpenGL
+/// path builds its first window surface here; the threaded and Vulkan
+/// renderers build theirs in `Env::create_surface`, which also rebuilds the
+/// surface of every renderer after a suspend.
+fn build_env(event_loop: &ActiveEventLoop, proxy: EventLoopProxy<ActionRequestEvent>) -> Env {
+    let window_attributes = WindowAttributes::default()
+        .with_title("rust-skia-gl-window")
+        .with_inner_size(winit::dpi::LogicalSize::new(600.0, 300.0));
 
     let template = ConfigTemplateBuilder::new()
         .with_alpha_size(8)
         .with_transparency(true);
 
-    let display_builder = DisplayBuilder::new().with_window_builder(Some(winit_window_builder));
+    let display_builder = DisplayBuilder::new().with_window_attributes(Some(window_attributes));
     let (window, gl_config) = display_builder
-        .build(&el, template, |configs| {
+        .build(event_loop, template, |configs| {
             configs
                 .reduce(|accum, config| {
                     let transparency_check = config.supports_transparency().unwrap_or(false)
@@ -78,7 +1015,6 @@ fn main() {
         NonZeroU32::new(600).unwrap(),
         NonZeroU32::new(300).unwrap(),
     );
-
     let gl_surface = unsafe {
         gl_config
             .display()
@@ -86,132 +1022,215 @@ fn main() {
             .expect("Could not create gl window surface")
     };
 
-    let gl_context = not_current_gl_context
-        .make_current(&gl_surface)
-        .expect("Could not make GL context current when setting up skia renderer");
+    let initial_size = {
+        let size = window.inner_size();
+        (size.width.max(1), size.height.max(1))
+    };
 
-    gl::load_with(|s| {
-        gl_config
-            .display()
-            .get_proc_address(CString::new(s).unwrap().as_c_str())
-    });
-    let interface = skia_safe::gpu::gl::Interface::new_load_with(|name| {
-        if name == "eglGetCurrentDisplay" {
-            return std::ptr::null();
-        }
-        gl_config
-            .display()
-            .get_proc_address(CString::new(name).unwrap().as_c_str())
-    })
-    .expect("Could not create interface");
+    let scene = hello_world_scene();
 
-    let mut gr_context = skia_safe::gpu::DirectContext::new_gl(Some(interface), None)
-        .expect("Could not create direct context");
+    // The adapter's initial tree closure yields the current content rather than
+    // an empty tree. Creating the adapter still swallows the first redraw
+    // request, which is the bug this example demonstrates; the `resumed` handler
+    // forces the initial draw regardless.
+    let initial_scene = scene.clone();
+    let adapter = Adapter::new(
+        &window,
+        move || initial_scene.tree_update(ROOT_ID),
+        proxy,
+    );
 
-    let fb_info = {
-        let mut fboid: GLint = 0;
-        unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
+    let renderer = if use_render_thread() {
+        // Hand the context over not-current; the worker makes it current on its
+        // own thread and builds skia there. A clone of the config stays here so
+        // we can build window surfaces to hand over on each resume.
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let worker_config = gl_config.clone();
+        let handle = std::thread::Builder::new()
+            .name("skia-render".to_string())
+            .spawn(move || {
+                render_worker(worker_config, not_current_gl_context, gl_surface, receiver)
+            })
+            .expect("Could not spawn render thread");
+        Renderer::Threaded(ThreadedRenderer {
+            sender,
+            handle: Some(handle),
+            gl_config,
+        })
+    } else if backend_kind() == BackendKind::Vulkan {
+        // The Vulkan backend owns its own instance/device and swapchain and does
+        // not use the glutin GL context. Drop it so the GL surface is released.
+        drop(not_current_gl_context);
+        drop(gl_surface);
+        Renderer::Inline(Box::new(VulkanBackend::new(&window)))
+    } else {
+        // Bind the context once so GL is loadable and we can build the
+        // `DirectContext`, which lives for the whole program.
+        let gl_context = not_current_gl_context
+            .make_current(&gl_surface)
+            .expect("Could not make GL context current when setting up skia renderer");
 
-        FramebufferInfo {
-            fboid: fboid.try_into().unwrap(),
-            format: skia_safe::gpu::gl::Format::RGBA8.into(),
-        }
-    };
+        gl::load_with(|s| {
+            gl_config
+                .display()
+                .get_proc_address(CString::new(s).unwrap().as_c_str())
+        });
+        let interface = skia_safe::gpu::gl::Interface::new_load_with(|name| {
+            if name == "eglGetCurrentDisplay" {
+                return std::ptr::null();
+            }
+            gl_config
+                .display()
+                .get_proc_address(CString::new(name).unwrap().as_c_str())
+        })
+        .expect("Could not create interface");
+
+        let mut gr_context = skia_safe::gpu::DirectContext::new_gl(Some(interface), None)
+            .expect("Could not create direct context");
 
-    window.set_inner_size(winit::dpi::Size::new(winit::dpi::LogicalSize::new(
-        600.0, 300.0,
-    )));
-
-    fn create_surface(
-        fb_info: FramebufferInfo,
-        gr_context: &mut skia_safe::gpu::DirectContext,
-        num_samples: usize,
-        stencil_size: usize,
-    ) -> Surface {
-        let size = (600, 300);
-        let backend_render_target =
-            BackendRenderTarget::new_gl(size, num_samples, stencil_size, fb_info);
-
-        Surface::from_backend_render_target(
+        let fb_info = {
+            let mut fboid: GLint = 0;
+            unsafe { gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut fboid) };
+
+            FramebufferInfo {
+                fboid: fboid.try_into().unwrap(),
+                format: skia_safe::gpu::gl::Format::RGBA8.into(),
+            }
+        };
+
+        let num_samples = gl_config.num_samples() as usize;
+        let stencil_size = gl_config.stencil_size() as usize;
+
+        let surface = create_surface(
+            fb_info,
+            &mut gr_context,
+            num_samples,
+            stencil_size,
+            initial_size.0,
+            initial_size.1,
+        );
+
+        Renderer::Inline(Box::new(GlBackend {
+            render: Some(RenderState {
+                surface,
+                gl_surface,
+                gl_context,
+            }),
+            not_current_context: None,
             gr_context,
-            &backend_render_target,
-            SurfaceOrigin::BottomLeft,
-            ColorType::RGBA8888,
-            None,
-            None,
-        )
-        .expect("Could not create skia surface")
+            gl_config,
+            fb_info,
+            num_samples,
+            stencil_size,
+        }))
+    };
+
+    Env {
+        renderer,
+        adapter,
+        scene,
+        font: load_font(),
+        focus: ROOT_ID,
+        window,
     }
-    let num_samples = gl_config.num_samples() as usize;
-    let stencil_size = gl_config.stencil_size() as usize;
+}
 
-    let surface = create_surface(fb_info, &mut gr_context, num_samples, stencil_size);
+/// Whether to relocate rendering to a dedicated thread, toggled with the
+/// `SKIA_RENDER_THREAD` environment variable.
+fn use_render_thread() -> bool {
+    std::env::var_os("SKIA_RENDER_THREAD").is_some()
+}
+
+/// The GPU backend skia draws on top of.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BackendKind {
+    Gl,
+    Vulkan,
+}
 
-    struct Env {
-        surface: Surface,
-        gl_surface: GlutinSurface<WindowSurface>,
-        gr_context: skia_safe::gpu::DirectContext,
-        gl_context: PossiblyCurrentContext,
-        #[allow(unused)]
-        window: Window,
+/// Pick the backend from `SKIA_BACKEND` (`vulkan` or `vk` selects Vulkan,
+/// anything else keeps the default OpenGL path).
+fn backend_kind() -> BackendKind {
+    match std::env::var("SKIA_BACKEND").as_deref() {
+        Ok("vulkan") | Ok("vk") => BackendKind::Vulkan,
+        _ => BackendKind::Gl,
     }
+}
 
-    // Simply calling creating the adapter will make the event loop never get the initial redraw request event.
-    // You can try removing this line to see how to the event actually gets emitted
-    let _adapter = Adapter::new(&window, || TreeUpdate::default(), el.create_proxy());
+struct App {
+    proxy: EventLoopProxy<ActionRequestEvent>,
+    env: Option<Env>,
+}
 
-    let mut env = Env {
-        surface,
-        gl_surface,
-        gl_context,
-        gr_context,
-        window,
-    };
+impl ApplicationHandler<ActionRequestEvent> for App {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let env = self
+            .env
+            .get_or_insert_with(|| build_env(event_loop, self.proxy.clone()));
+        // Rebuild the surface if we are resuming after a suspend, then force
+        // the initial draw rather than waiting for a `RedrawRequested` the
+        // AccessKit adapter may have swallowed.
+        env.create_surface();
+        env.window.request_redraw();
+    }
 
-    el.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
+    fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(env) = self.env.as_mut() {
+            env.destroy_surface();
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &ActiveEventLoop, event: ActionRequestEvent) {
+        if let Some(env) = self.env.as_mut() {
+            env.handle_action_request(event.request);
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(env) = self.env.as_mut() {
+            if env.scene.dirty {
+                env.window.request_redraw();
+            }
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let Some(env) = self.env.as_mut() else {
+            return;
+        };
+
+        env.adapter.process_event(&env.window, &event);
 
         match event {
-            Event::LoopDestroyed => {}
-            Event::WindowEvent { event, .. } => match event {
-                WindowEvent::CloseRequested => {
-                    *control_flow = ControlFlow::Exit;
-                }
-                WindowEvent::Resized(physical_size) => {
-                    env.surface =
-                        create_surface(fb_info, &mut env.gr_context, num_samples, stencil_size);
-                    /* First resize the opengl drawable */
-                    let (width, height): (u32, u32) = physical_size.into();
-
-                    env.gl_surface.resize(
-                        &env.gl_context,
-                        NonZeroU32::new(width.max(1)).unwrap(),
-                        NonZeroU32::new(height.max(1)).unwrap(),
-                    );
-                }
-                _ => (),
-            },
-            Event::RedrawRequested(_) => {
-                println!("DRAWING");
-                let canvas = env.surface.canvas();
-                canvas.clear(Color::WHITE);
-                let mut paint = Paint::default();
-                paint.set_color(Color::BLUE);
-                let mgr = FontMgr::default();
-                let mut font_coll = FontCollection::new();
-                font_coll.set_default_font_manager(mgr, "Inter");
-                let font = Font::from_typeface(
-                    font_coll
-                        .find_typefaces(&["Inter"], FontStyle::default())
-                        .first()
-                        .unwrap(),
-                    100.0,
-                );
-                canvas.draw_str("Hello World", Point::new(30.0, 150.0), &font, &paint);
-                env.gr_context.flush_and_submit();
-                env.gl_surface.swap_buffers(&env.gl_context).unwrap();
+            WindowEvent::CloseRequested => {
+                event_loop.exit();
+            }
+            WindowEvent::Resized(physical_size) => {
+                let (width, height): (u32, u32) = physical_size.into();
+                env.resize(width, height);
+            }
+            WindowEvent::RedrawRequested => {
+                env.draw();
+                env.update_accessibility();
             }
             _ => (),
         }
-    });
+    }
+}
+
+fn main() {
+    let el = EventLoop::<ActionRequestEvent>::with_user_event().build().unwrap();
+    el.set_control_flow(ControlFlow::Wait);
+
+    let mut app = App {
+        proxy: el.create_proxy(),
+        env: None,
+    };
+
+    el.run_app(&mut app).unwrap();
 }